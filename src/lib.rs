@@ -9,10 +9,14 @@ extern crate byteorder;
 extern crate clear_on_drop;
 extern crate core;
 extern crate keccak;
+extern crate rand_core;
 
 #[cfg(test)]
 extern crate strobe_rs;
 
+use rand_core::impls;
+use rand_core::{CryptoRng, RngCore};
+
 mod strobe;
 
 use strobe::Strobe128;
@@ -27,7 +31,61 @@ fn encode_usize(x: usize) -> [u8; 4] {
     buf
 }
 
-/// A transcript of a public-coin argument.
+fn encode_u64(x: u64) -> [u8; 8] {
+    use byteorder::{ByteOrder, LittleEndian};
+
+    let mut buf = [0; 8];
+    LittleEndian::write_u64(&mut buf, x);
+    buf
+}
+
+/// The primitive operations a [`GenericTranscript`] needs from its
+/// underlying hash construction: initializing with a domain-separation
+/// label, absorbing a labeled message, and squeezing labeled challenge
+/// bytes. This mirrors the `SigningTranscript` abstraction, which lets a
+/// transcript be backed either by a duplex sponge or by a conventional
+/// hash function.
+///
+/// The default backend is [`Strobe128`]. Implementing this trait for
+/// another type lets [`GenericTranscript`] run in environments without
+/// keccak, or on top of a specific standardized hash (e.g. SHA-3 via a
+/// plain Merkle–Damgård/merge construction), while keeping the exact
+/// same `new`/`commit`/`challenge` API and framing (length-prefixed
+/// `meta_ad` + `ad`/`prf`).
+pub trait TranscriptBackend: Clone {
+    /// Initialize a new backend state, domain-separated by `label`.
+    fn new(label: &[u8]) -> Self;
+
+    /// Absorb `data` into the backend state, framed as metadata.
+    fn meta_ad(&mut self, data: &[u8], more: bool);
+
+    /// Absorb `data` into the backend state as an ordinary message.
+    fn ad(&mut self, data: &[u8], more: bool);
+
+    /// Squeeze challenge bytes out of the backend state into `data`.
+    fn prf(&mut self, data: &mut [u8], more: bool);
+}
+
+impl TranscriptBackend for Strobe128 {
+    fn new(label: &[u8]) -> Strobe128 {
+        Strobe128::new(label)
+    }
+
+    fn meta_ad(&mut self, data: &[u8], more: bool) {
+        Strobe128::meta_ad(self, data, more)
+    }
+
+    fn ad(&mut self, data: &[u8], more: bool) {
+        Strobe128::ad(self, data, more)
+    }
+
+    fn prf(&mut self, data: &mut [u8], more: bool) {
+        Strobe128::prf(self, data, more)
+    }
+}
+
+/// A transcript of a public-coin argument, generic over the underlying
+/// [`TranscriptBackend`].
 ///
 /// The prover's messages are added to the transcript using `commit`,
 /// and the verifier's challenges can be computed using `challenge`.
@@ -97,11 +155,16 @@ fn encode_usize(x: usize) -> [u8; 4] {
 /// protocol-specific trait, different protocols can use the same
 /// `Transcript` instance without imposing any extra type constraints.
 #[derive(Clone)]
-pub struct Transcript {
-    strobe: Strobe128,
+pub struct GenericTranscript<B: TranscriptBackend = Strobe128> {
+    backend: B,
 }
 
-impl Transcript {
+/// The default [`GenericTranscript`], backed by [`Strobe128`]. This is
+/// the type most protocols should use; see [`GenericTranscript`] for the
+/// full documentation.
+pub type Transcript = GenericTranscript<Strobe128>;
+
+impl<B: TranscriptBackend> GenericTranscript<B> {
     /// Initialize a new transcript with the supplied `label`, which
     /// is used as a domain separator.
     ///
@@ -109,9 +172,9 @@ impl Transcript {
     ///
     /// This function should be called by a protocol's API consumer,
     /// and *not* by the protocol implementation.
-    pub fn new(label: &[u8]) -> Transcript {
-        Transcript {
-            strobe: Strobe128::new(label),
+    pub fn new(label: &[u8]) -> Self {
+        GenericTranscript {
+            backend: B::new(label),
         }
     }
 
@@ -121,9 +184,9 @@ impl Transcript {
     /// also committed to the transcript.
     pub fn commit(&mut self, label: &[u8], message: &[u8]) {
         let data_len = encode_usize(message.len());
-        self.strobe.meta_ad(label, false);
-        self.strobe.meta_ad(&data_len, true);
-        self.strobe.ad(message, false);
+        self.backend.meta_ad(label, false);
+        self.backend.meta_ad(&data_len, true);
+        self.backend.ad(message, false);
     }
 
     /// Fill the supplied buffer with the verifier's challenge bytes.
@@ -132,12 +195,188 @@ impl Transcript {
     /// also committed to the transcript.
     pub fn challenge(&mut self, label: &[u8], challenge_bytes: &mut [u8]) {
         let data_len = encode_usize(challenge_bytes.len());
+        self.backend.meta_ad(label, false);
+        self.backend.meta_ad(&data_len, true);
+        self.backend.prf(challenge_bytes, false);
+    }
+
+    /// Commit a `u64` to the transcript, encoded as 8 bytes
+    /// little-endian.
+    ///
+    /// This is the canonical way to frame protocol constants such as a
+    /// rangeproof's bit-length `n`, a party count `m`, or a circuit
+    /// size: every consumer using `commit_u64` gets identical,
+    /// interoperable transcript bytes, instead of hand-rolled
+    /// little-endian encodings that diverge between protocols. Unlike
+    /// `commit`, which is bounded by `encode_usize`'s `u32::MAX` cap on
+    /// message lengths, `commit_u64` supports the full 64-bit range of
+    /// `x` itself.
+    pub fn commit_u64(&mut self, label: &[u8], x: u64) {
+        let data = encode_u64(x);
+        self.commit(label, &data);
+    }
+
+    /// Commit an explicit domain-separation `label` to the transcript.
+    ///
+    /// This is a convenience wrapper around `commit` for protocols that
+    /// want a standalone domain-separator string (e.g.
+    /// `b"rangeproof v1"`) distinct from the parameters committed
+    /// alongside it via `commit_u64`.
+    pub fn domain_sep(&mut self, label: &[u8]) {
+        self.commit(b"dom-sep", label);
+    }
+
+    /// Validate `bytes` with the caller-supplied `validator` before
+    /// committing them to the transcript, and propagate a validation
+    /// failure instead of absorbing rejected data.
+    ///
+    /// Protocols built on top of this crate (bulletproofs, zkp, and
+    /// others) each reimplement a `validate_and_append_point` that
+    /// rejects identity/malformed point encodings. The "validate, then
+    /// append" ordering is security-critical: a point that fails
+    /// validation must never be absorbed, since the transcript must not
+    /// reflect data the verifier ultimately rejects. Enforcing that
+    /// ordering once here, rather than leaving every downstream crate to
+    /// get it right independently, is the whole point of this method;
+    /// it is otherwise curve-agnostic; `validator` can check anything
+    /// about `bytes` the caller needs (e.g. "is not the identity point",
+    /// "is a canonical encoding").
+    pub fn validate_and_commit<E>(
+        &mut self,
+        label: &[u8],
+        bytes: &[u8],
+        validator: impl Fn(&[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        validator(bytes)?;
+        self.commit(label, bytes);
+        Ok(())
+    }
+
+    /// Fill `challenge_bytes` with 64 bytes of challenge output, for
+    /// protocols that reduce a wide challenge into a scalar via a
+    /// uniform (mod-order) reduction.
+    ///
+    /// This is the byte-oriented counterpart of the `challenge_scalar`
+    /// pattern used by extension traits such as the one in this crate's
+    /// documentation; unlike that pattern it does not depend on any
+    /// particular elliptic-curve crate, leaving the reduction itself to
+    /// the caller.
+    pub fn challenge_scalar_wide(&mut self, label: &[u8], challenge_bytes: &mut [u8; 64]) {
+        self.challenge(label, challenge_bytes);
+    }
+}
+
+impl Transcript {
+    /// Fork the current [`Transcript`] state to begin an RNG that
+    /// derives signing nonces bound to this transcript, a prover's
+    /// secret witness data, and fresh external entropy.
+    ///
+    /// Because the transcript state is itself bound to the protocol's
+    /// statement (via prior `commit` and `challenge` calls), nonces
+    /// produced from it are deterministic given the witness, unique per
+    /// statement, and still hedged against a broken system RNG, since
+    /// `finalize` mixes in caller-supplied randomness as well.
+    ///
+    /// This is tied to the [`Strobe128`] backend specifically, since it
+    /// relies on STROBE's `key` operation, which isn't one of the three
+    /// primitives exposed by [`TranscriptBackend`].
+    pub fn build_rng(&self) -> TranscriptRngBuilder {
+        TranscriptRngBuilder {
+            strobe: self.backend.clone(),
+        }
+    }
+}
+
+/// Constructs a [`TranscriptRng`] by rekeying a [`Transcript`] with
+/// prover secrets and an external RNG.
+///
+/// This is a direct implementation of the Fiat-Shamir witness
+/// construction used by schnorrkel's `witness_bytes_rng`: every witness
+/// value the prover folds in, plus the transcript state leading up to
+/// this point, binds the resulting nonces to the statement being
+/// proved, so that reusing a nonce across different statements (or
+/// different witnesses for the same statement) is infeasible.
+pub struct TranscriptRngBuilder {
+    strobe: Strobe128,
+}
+
+impl TranscriptRngBuilder {
+    /// Mix the `witness` bytes into the transcript state being used to
+    /// build the [`TranscriptRng`].
+    ///
+    /// The `label` parameter is metadata about `witness`, and is also
+    /// committed to the transcript.
+    pub fn rekey_with_witness_bytes(mut self, label: &[u8], witness: &[u8]) -> Self {
+        let witness_len = encode_usize(witness.len());
         self.strobe.meta_ad(label, false);
-        self.strobe.meta_ad(&data_len, true);
-        self.strobe.prf(challenge_bytes, false);
+        self.strobe.meta_ad(&witness_len, true);
+        self.strobe.key(witness, false);
+        self
+    }
+
+    /// Use the supplied external `rng` to rekey the transcript state
+    /// one last time, and return the resulting [`TranscriptRng`].
+    ///
+    /// Mixing in fresh entropy from `rng` at this point means the
+    /// output nonces are hedged: even if `rng` turns out to be
+    /// predictable, the nonces remain bound to the transcript and
+    /// witness data, and even if the witness/transcript binding were
+    /// somehow replayed, the fresh entropy keeps nonces from repeating.
+    pub fn finalize<R>(mut self, rng: &mut R) -> TranscriptRng
+    where
+        R: RngCore + CryptoRng,
+    {
+        let random_bytes = {
+            let mut bytes = [0u8; 32];
+            rng.fill_bytes(&mut bytes);
+            bytes
+        };
+
+        self.strobe.meta_ad(b"rng", false);
+        self.strobe.key(&random_bytes, false);
+
+        TranscriptRng {
+            strobe: self.strobe,
+        }
     }
 }
 
+/// An RNG providing synthetic nonces derived from a transcript.
+///
+/// The RNG runs on a copy of the transcript state taken when
+/// [`Transcript::build_rng`] was called, so its output bytes depend on
+/// that transcript state, any witness data used to build the RNG, the
+/// auxiliary entropy, and the lengths of earlier draws from it.
+/// Messages committed to the [`Transcript`] after `build_rng` do not
+/// affect it.
+#[derive(Clone)]
+pub struct TranscriptRng {
+    strobe: Strobe128,
+}
+
+impl RngCore for TranscriptRng {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let dest_len = encode_usize(dest.len());
+        self.strobe.meta_ad(&dest_len, false);
+        self.strobe.prf(dest, false);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for TranscriptRng {}
+
 #[cfg(test)]
 mod tests {
     use strobe_rs::OpFlags;
@@ -0,0 +1,156 @@
+use byteorder::{ByteOrder, LittleEndian};
+use clear_on_drop::clear::Clear;
+use keccak;
+
+/// Strobe R value; security parameter for 128-bit security.  Equal to 166.
+const STROBE_R: u8 = 166;
+
+const FLAG_I: u8 = 1;
+const FLAG_A: u8 = 1 << 1;
+const FLAG_C: u8 = 1 << 2;
+const FLAG_T: u8 = 1 << 3;
+const FLAG_M: u8 = 1 << 4;
+const FLAG_K: u8 = 1 << 5;
+
+#[derive(Clone)]
+pub struct Strobe128 {
+    state: [u8; 200],
+    pos: u8,
+    pos_begin: u8,
+    cur_flags: u8,
+}
+
+impl ::core::fmt::Debug for Strobe128 {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        // Do not print the state; should be a secret
+        write!(f, "Strobe128: STATE OMITTED")
+    }
+}
+
+impl Strobe128 {
+    pub fn new(protocol_label: &[u8]) -> Strobe128 {
+        let mut state = [0u8; 200];
+        state[0..6].copy_from_slice(&[1, STROBE_R + 2, 1, 0, 1, 96]);
+        state[6..18].copy_from_slice(b"STROBEv1.0.2");
+        Strobe128::run_f_on(&mut state);
+
+        let mut strobe = Strobe128 {
+            state,
+            pos: 0,
+            pos_begin: 0,
+            cur_flags: 0,
+        };
+
+        strobe.meta_ad(protocol_label, false);
+
+        strobe
+    }
+
+    /// Absorb `data` into the sponge state, with `data` framed as
+    /// metadata (e.g. operation labels and lengths) rather than as a
+    /// message.
+    pub fn meta_ad(&mut self, data: &[u8], more: bool) {
+        self.begin_op(FLAG_M | FLAG_A, more);
+        self.absorb(data);
+    }
+
+    /// Absorb `data` into the sponge state as associated data.
+    pub fn ad(&mut self, data: &[u8], more: bool) {
+        self.begin_op(FLAG_A, more);
+        self.absorb(data);
+    }
+
+    /// Key the sponge state with `data`, irreversibly mixing it into
+    /// the running state rather than just absorbing it.
+    pub fn key(&mut self, data: &[u8], more: bool) {
+        self.begin_op(FLAG_A | FLAG_C, more);
+        self.overwrite(data);
+    }
+
+    /// Squeeze output bytes from the sponge state into `data`.
+    pub fn prf(&mut self, data: &mut [u8], more: bool) {
+        self.begin_op(FLAG_I | FLAG_A | FLAG_C, more);
+        self.squeeze(data);
+    }
+
+    fn run_f(&mut self) {
+        self.state[self.pos as usize] ^= self.pos_begin;
+        self.state[(self.pos + 1) as usize] ^= 0x04;
+        self.state[(STROBE_R + 1) as usize] ^= 0x80;
+        Strobe128::run_f_on(&mut self.state);
+        self.pos = 0;
+        self.pos_begin = 0;
+    }
+
+    fn run_f_on(state: &mut [u8; 200]) {
+        let mut state64 = [0u64; 25];
+        LittleEndian::read_u64_into(state, &mut state64);
+        keccak::f1600(&mut state64);
+        LittleEndian::write_u64_into(&state64, state);
+    }
+
+    fn absorb(&mut self, data: &[u8]) {
+        for byte in data {
+            self.state[self.pos as usize] ^= byte;
+            self.pos += 1;
+            if self.pos == STROBE_R {
+                self.run_f();
+            }
+        }
+    }
+
+    fn overwrite(&mut self, data: &[u8]) {
+        for byte in data {
+            self.state[self.pos as usize] = *byte;
+            self.pos += 1;
+            if self.pos == STROBE_R {
+                self.run_f();
+            }
+        }
+    }
+
+    fn squeeze(&mut self, data: &mut [u8]) {
+        for byte in data {
+            *byte = self.state[self.pos as usize];
+            self.state[self.pos as usize] = 0;
+            self.pos += 1;
+            if self.pos == STROBE_R {
+                self.run_f();
+            }
+        }
+    }
+
+    fn begin_op(&mut self, flags: u8, more: bool) {
+        if more {
+            debug_assert_eq!(
+                self.cur_flags, flags,
+                "You must not change flags while continuing"
+            );
+            return;
+        }
+
+        // We don't support switching direction mid-operation.
+        debug_assert_eq!(flags & FLAG_T, 0, "T flag not supported");
+
+        let old_begin = self.pos_begin;
+        self.pos_begin = self.pos + 1;
+        self.cur_flags = flags;
+
+        self.absorb(&[old_begin, flags]);
+
+        let force_f = 0 != (flags & (FLAG_C | FLAG_K));
+
+        if force_f && self.pos != 0 {
+            self.run_f();
+        }
+    }
+}
+
+impl Drop for Strobe128 {
+    fn drop(&mut self) {
+        self.state.clear();
+        self.pos.clear();
+        self.pos_begin.clear();
+        self.cur_flags.clear();
+    }
+}